@@ -1,4 +1,7 @@
 use hyper_transpiler::transpile as rust_transpile;
+use hyper_transpiler::transpile_checked as rust_transpile_checked;
+use hyper_transpiler::Severity as RustSeverity;
+use hyper_transpiler::TranspileResult as RustTranspileResult;
 use pyo3::prelude::*;
 
 #[pyclass]
@@ -14,6 +17,36 @@ pub struct SourceMapping {
     pub src_col: usize,
 }
 
+#[pyclass]
+#[derive(Clone)]
+pub struct Diagnostic {
+    #[pyo3(get)]
+    pub severity: String,
+    #[pyo3(get)]
+    pub message: String,
+    #[pyo3(get)]
+    pub src_line: usize,
+    #[pyo3(get)]
+    pub src_col: usize,
+    #[pyo3(get)]
+    pub byte_offset: usize,
+    #[pyo3(get)]
+    pub len: usize,
+}
+
+#[pyclass]
+#[derive(Clone)]
+pub struct SourceMapV3 {
+    #[pyo3(get)]
+    pub version: u8,
+    #[pyo3(get)]
+    pub sources: Vec<String>,
+    #[pyo3(get)]
+    pub names: Vec<String>,
+    #[pyo3(get)]
+    pub mappings: String,
+}
+
 #[pyclass]
 #[derive(Clone)]
 pub struct TranspileResult {
@@ -21,11 +54,13 @@ pub struct TranspileResult {
     pub python_code: String,
     #[pyo3(get)]
     pub source_mappings: Vec<SourceMapping>,
+    #[pyo3(get)]
+    pub diagnostics: Vec<Diagnostic>,
+    #[pyo3(get)]
+    pub sourcemap_v3: SourceMapV3,
 }
 
-#[pyfunction]
-fn transpile(source: &str) -> TranspileResult {
-    let result = rust_transpile(source);
+fn convert(result: RustTranspileResult) -> TranspileResult {
     TranspileResult {
         python_code: result.python_code,
         source_mappings: result
@@ -38,13 +73,49 @@ fn transpile(source: &str) -> TranspileResult {
                 src_col: m.src_col,
             })
             .collect(),
+        diagnostics: result
+            .diagnostics
+            .into_iter()
+            .map(|d| Diagnostic {
+                severity: match d.severity {
+                    RustSeverity::Error => "error".to_string(),
+                    RustSeverity::Warning => "warning".to_string(),
+                },
+                message: d.message,
+                src_line: d.src_line,
+                src_col: d.src_col,
+                byte_offset: d.byte_offset,
+                len: d.len,
+            })
+            .collect(),
+        sourcemap_v3: SourceMapV3 {
+            version: result.sourcemap_v3.version,
+            sources: result.sourcemap_v3.sources,
+            names: result.sourcemap_v3.names,
+            mappings: result.sourcemap_v3.mappings,
+        },
     }
 }
 
+#[pyfunction]
+fn transpile(source: &str) -> TranspileResult {
+    convert(rust_transpile(source))
+}
+
+/// Transpile and run scope/symbol analysis, surfacing undeclared and unused
+/// names alongside the usual diagnostics.
+#[pyfunction]
+fn transpile_checked(source: &str) -> TranspileResult {
+    convert(rust_transpile_checked(source))
+}
+
 #[pymodule]
 fn _hyper_native(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(transpile, m)?)?;
+    m.add_function(wrap_pyfunction!(transpile_checked, m)?)?;
     m.add_class::<TranspileResult>()?;
     m.add_class::<SourceMapping>()?;
+    m.add_class::<Diagnostic>()?;
+    m.add_class::<SourceMapV3>()?;
     Ok(())
 }
@@ -1,6 +1,7 @@
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde::Serialize;
+use std::collections::HashSet;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LineType {
@@ -35,6 +36,35 @@ pub struct PythonPiece {
     pub src_end: usize,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A transpile-time problem, located in the original `.hyper` source so the
+/// CLI can render it as a caret-underlined snippet.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub src_line: usize,
+    pub src_col: usize,
+    pub byte_offset: usize,
+    pub len: usize,
+}
+
+/// Standard [Source Map v3](https://sourcemaps.info/spec.html) JSON, for
+/// tooling (browsers, debuggers, other LSP clients) that doesn't know about
+/// our own `SourceMapping` shape.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceMapV3 {
+    pub version: u8,
+    pub sources: Vec<String>,
+    pub names: Vec<String>,
+    pub mappings: String,
+}
 
 #[derive(Debug, Clone, Serialize)]
 pub struct TranspileResult {
@@ -42,6 +72,8 @@ pub struct TranspileResult {
     pub source_mappings: Vec<SourceMapping>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub python_pieces: Option<Vec<PythonPiece>>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub sourcemap_v3: SourceMapV3,
 }
 
 lazy_static! {
@@ -55,6 +87,25 @@ lazy_static! {
     static ref AWAIT_INLINE: Regex = Regex::new(r"[=(\[,:]\s*await\s").unwrap();
     static ref ASYNC_FOR: Regex = Regex::new(r"^\s*async\s+for\s").unwrap();
     static ref ASYNC_WITH: Regex = Regex::new(r"^\s*async\s+with\s").unwrap();
+    static ref FOR_TARGET: Regex = Regex::new(r"^(?:async\s+)?for\s+(.+?)\s+in\s").unwrap();
+    static ref AS_BINDER: Regex = Regex::new(r"\bas\s+([a-zA-Z_][a-zA-Z0-9_]*)").unwrap();
+    static ref ASSIGN_TARGET: Regex =
+        Regex::new(r"^([a-zA-Z_][a-zA-Z0-9_]*(?:\s*,\s*[a-zA-Z_][a-zA-Z0-9_]*)*)\s*(?::[^=]+)?=").unwrap();
+    static ref IDENT: Regex = Regex::new(r"[a-zA-Z_][a-zA-Z0-9_]*").unwrap();
+}
+
+/// Names that are always in scope and shouldn't trigger an undeclared-name
+/// diagnostic when they show up inside a `{...}` interpolation.
+const PY_BUILTINS: &[&str] = &[
+    "True", "False", "None", "self", "cls", "len", "str", "int", "float", "bool", "bytes",
+    "range", "list", "dict", "set", "tuple", "frozenset", "enumerate", "zip", "print",
+    "isinstance", "issubclass", "type", "sum", "min", "max", "sorted", "reversed", "any", "all",
+    "map", "filter", "abs", "round", "super", "repr", "format", "hasattr", "getattr", "setattr",
+    "open", "iter", "next", "and", "or", "not", "in", "is", "if", "else", "for", "lambda",
+];
+
+fn is_keyword_or_builtin(name: &str) -> bool {
+    PY_BUILTINS.contains(&name)
 }
 
 fn classify_line(text: &str) -> LineType {
@@ -69,19 +120,106 @@ fn classify_line(text: &str) -> LineType {
     }
 }
 
+/// Bracket/string state carried across physical lines while collecting a
+/// single logical line.
+#[derive(Default)]
+struct ScanState {
+    bracket_depth: i32,
+    open_string: Option<(char, bool)>, // (quote char, is triple-quoted)
+}
+
+impl ScanState {
+    fn is_open(&self) -> bool {
+        self.bracket_depth > 0 || self.open_string.is_some()
+    }
+}
+
+/// Scan one physical line, updating bracket depth and open-string state.
+/// Returns whether the line ends in a `\` continuation.
+fn scan_line(text: &str, state: &mut ScanState) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if let Some((quote, triple)) = state.open_string {
+            if c == '\\' {
+                i += 2;
+            } else if c == quote && (!triple || (i + 2 < chars.len() && chars[i + 1] == quote && chars[i + 2] == quote)) {
+                state.open_string = None;
+                i += if triple { 3 } else { 1 };
+            } else {
+                i += 1;
+            }
+            continue;
+        }
+
+        match c {
+            '#' => break,
+            '(' | '[' | '{' => {
+                state.bracket_depth += 1;
+                i += 1;
+            }
+            ')' | ']' | '}' => {
+                state.bracket_depth = (state.bracket_depth - 1).max(0);
+                i += 1;
+            }
+            '"' | '\'' => {
+                let triple = i + 2 < chars.len() && chars[i + 1] == c && chars[i + 2] == c;
+                state.open_string = Some((c, triple));
+                i += if triple { 3 } else { 1 };
+            }
+            '\\' if i == chars.len() - 1 => {
+                return true;
+            }
+            _ => {
+                i += 1;
+            }
+        }
+    }
+
+    false
+}
+
+/// Split `source` into logical lines. A Python statement broken across
+/// physical lines (trailing `\`, an unclosed bracket, or a multi-line
+/// triple-quoted string) is collected into one [`Line`] whose `text`
+/// preserves the internal newlines, so the body emitter indents it once
+/// instead of re-indenting every physical line as its own statement.
 fn lex(source: &str) -> Vec<Line> {
     let mut lines = Vec::new();
     let mut byte_offset = 0;
+    let mut physical = source.lines().enumerate().peekable();
+
+    while let Some((i, text)) = physical.next() {
+        let line_type = classify_line(text);
+        let start_offset = byte_offset;
+        let mut combined = text.to_string();
+        byte_offset += text.len() + 1; // +1 for \n
+
+        // Bracket/quote continuation only makes sense for actual Python
+        // source (`Control`/`Python` lines). `Html` lines are literal markup
+        // and prose text, where an apostrophe (`it's`) or an unmatched `(`
+        // is completely ordinary and must not fold the following source
+        // lines into the same logical line.
+        let mut state = ScanState::default();
+        let mut continues = matches!(line_type, LineType::Control | LineType::Python) && scan_line(text, &mut state);
+
+        while (continues || state.is_open()) && physical.peek().is_some() {
+            let (_, next_text) = physical.next().unwrap();
+            combined.push('\n');
+            combined.push_str(next_text);
+            byte_offset += next_text.len() + 1;
+            continues = scan_line(next_text, &mut state);
+        }
 
-    for (i, text) in source.lines().enumerate() {
         lines.push(Line {
-            line_type: classify_line(text),
-            text: text.to_string(),
+            line_type,
+            text: combined,
             line_number: i,
-            byte_offset,
+            byte_offset: start_offset,
         });
-        // Account for line content plus newline character
-        byte_offset += text.len() + 1; // +1 for \n
     }
 
     lines
@@ -139,6 +277,84 @@ fn find_structure(lines: &[Line]) -> (Vec<&Line>, Vec<&Line>, usize) {
     (leading, params, body_start)
 }
 
+/// One open block on the indentation stack, kept alongside where it was
+/// opened so an unclosed block can point back at its source line.
+struct BlockFrame {
+    kind: &'static str,
+    line_number: usize,
+    col: usize,
+    byte_offset: usize,
+}
+
+const BASE64_VLQ_ALPHABET: &[u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Append `value`'s base64-VLQ encoding (sign in the low bit, continuation
+/// in the high bit of each 6-bit digit) to `out`.
+fn push_vlq(value: i64, out: &mut String) {
+    let mut vlq = if value < 0 { ((-value) << 1) | 1 } else { value << 1 };
+    loop {
+        let mut digit = (vlq & 0x1f) as usize;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 0x20;
+        }
+        out.push(BASE64_VLQ_ALPHABET[digit] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+/// Convert our `SourceMapping`s into a Source Map v3 `mappings` string:
+/// group by `gen_line`, sort each group by `gen_col`, and VLQ-encode
+/// `[genCol, sourceIndex, srcLine, srcCol]` as deltas from the previous
+/// segment. `gen_col` resets to 0 per generated line; the rest persist as
+/// running totals. Generated lines with no mapping get an empty group.
+pub fn source_map_v3(mappings: &[SourceMapping], source_name: &str) -> SourceMapV3 {
+    let mut by_line: std::collections::BTreeMap<usize, Vec<&SourceMapping>> =
+        std::collections::BTreeMap::new();
+    for m in mappings {
+        by_line.entry(m.gen_line).or_default().push(m);
+    }
+
+    let mut out = String::new();
+    let mut prev_src_line = 0i64;
+    let mut prev_src_col = 0i64;
+
+    if let Some(&max_line) = by_line.keys().max() {
+        for gen_line in 0..=max_line {
+            if gen_line > 0 {
+                out.push(';');
+            }
+            let Some(group) = by_line.get(&gen_line) else { continue };
+            let mut sorted = group.clone();
+            sorted.sort_by_key(|m| m.gen_col);
+
+            let mut prev_gen_col = 0i64;
+            for (i, m) in sorted.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                push_vlq(m.gen_col as i64 - prev_gen_col, &mut out);
+                push_vlq(0, &mut out); // single source, index delta is always 0
+                push_vlq(m.src_line as i64 - prev_src_line, &mut out);
+                push_vlq(m.src_col as i64 - prev_src_col, &mut out);
+                prev_gen_col = m.gen_col as i64;
+                prev_src_line = m.src_line as i64;
+                prev_src_col = m.src_col as i64;
+            }
+        }
+    }
+
+    SourceMapV3 {
+        version: 3,
+        sources: vec![source_name.to_string()],
+        names: Vec::new(),
+        mappings: out,
+    }
+}
+
 fn content_bounds(text: &str) -> (usize, usize) {
     let bytes = text.as_bytes();
     let start = bytes.iter().take_while(|&&b| b == b' ' || b == b'\t').count();
@@ -271,11 +487,17 @@ pub fn transpile_ext(source: &str, include_injection: bool) -> TranspileResult {
 
     // Body
     let mut level = 1usize;
-    let mut stack: Vec<&str> = Vec::new();
+    let mut stack: Vec<BlockFrame> = Vec::new();
+    let mut diagnostics = Vec::new();
 
     for i in body_start..lines.len() {
         let line = &lines[i];
         let (start, end) = content_bounds(&line.text);
+        // lex() may have folded several physical source lines (a bracketed
+        // or backslash-continued statement) into this one Line; its text
+        // still carries their internal newlines straight into python_code,
+        // so out_line must advance by all of them, not just one.
+        let line_span = line.text.matches('\n').count() + 1;
 
         if start >= end {
             output.push(String::new());
@@ -293,7 +515,7 @@ pub fn transpile_ext(source: &str, include_injection: bool) -> TranspileResult {
                     src_end: line.byte_offset,
                 });
             }
-            out_line += 1;
+            out_line += line_span;
             continue;
         }
 
@@ -326,10 +548,20 @@ pub fn transpile_ext(source: &str, include_injection: bool) -> TranspileResult {
                         });
                     }
                 } else if is_case {
-                    if stack.last() == Some(&"case") {
+                    if stack.last().map(|f| f.kind) == Some("case") {
                         stack.pop();
                         level = level.saturating_sub(1);
                     }
+                    if stack.last().map(|f| f.kind) != Some("match") {
+                        diagnostics.push(Diagnostic {
+                            severity: Severity::Error,
+                            message: "`case` outside of `match`".to_string(),
+                            src_line: line.line_number,
+                            src_col: start,
+                            byte_offset: src_start,
+                            len: "case".len(),
+                        });
+                    }
                     output.push(format!("{}{}", indent.repeat(level), trimmed));
                     mappings.push(SourceMapping {
                         gen_line: out_line,
@@ -345,7 +577,12 @@ pub fn transpile_ext(source: &str, include_injection: bool) -> TranspileResult {
                             src_end,
                         });
                     }
-                    stack.push("case");
+                    stack.push(BlockFrame {
+                        kind: "case",
+                        line_number: line.line_number,
+                        col: start,
+                        byte_offset: src_start,
+                    });
                     level += 1;
                 } else {
                     output.push(format!("{}{}", indent.repeat(level), trimmed));
@@ -363,17 +600,31 @@ pub fn transpile_ext(source: &str, include_injection: bool) -> TranspileResult {
                             src_end,
                         });
                     }
-                    stack.push(if trimmed.starts_with("match") { "match" } else { "block" });
+                    stack.push(BlockFrame {
+                        kind: if trimmed.starts_with("match") { "match" } else { "block" },
+                        line_number: line.line_number,
+                        col: start,
+                        byte_offset: src_start,
+                    });
                     level += 1;
                 }
             }
 
             LineType::End => {
-                while stack.last() == Some(&"case") {
+                while stack.last().map(|f| f.kind) == Some("case") {
                     stack.pop();
                     level = level.saturating_sub(1);
                 }
-                if !stack.is_empty() {
+                if stack.is_empty() {
+                    diagnostics.push(Diagnostic {
+                        severity: Severity::Error,
+                        message: "unexpected `end` with no open block".to_string(),
+                        src_line: line.line_number,
+                        src_col: start,
+                        byte_offset: src_start,
+                        len: "end".len(),
+                    });
+                } else {
                     stack.pop();
                     level = level.saturating_sub(1);
                 }
@@ -414,6 +665,25 @@ pub fn transpile_ext(source: &str, include_injection: bool) -> TranspileResult {
             }
 
             LineType::Python => {
+                // `name: type` with nothing after it reads like a parameter
+                // annotation the author meant to put before the template
+                // body. `name: type = value` is an ordinary annotated
+                // assignment — valid Python that's emitted verbatim — so it
+                // must not trigger this warning.
+                if let Some(caps) = TYPE_ANNOTATION.captures(trimmed).filter(|caps| !caps[2].contains('=')) {
+                    let name = caps.get(1).unwrap();
+                    diagnostics.push(Diagnostic {
+                        severity: Severity::Warning,
+                        message: format!(
+                            "parameter annotation `{}` has no effect after the template body has started",
+                            name.as_str()
+                        ),
+                        src_line: line.line_number,
+                        src_col: start,
+                        byte_offset: src_start,
+                        len: trimmed.len(),
+                    });
+                }
                 output.push(format!("{}{}", indent.repeat(level), trimmed));
                 mappings.push(SourceMapping {
                     gen_line: out_line,
@@ -432,7 +702,18 @@ pub fn transpile_ext(source: &str, include_injection: bool) -> TranspileResult {
             }
         }
 
-        out_line += 1;
+        out_line += line_span;
+    }
+
+    for frame in &stack {
+        diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            message: format!("unclosed `{}` has no matching `end`", frame.kind),
+            src_line: frame.line_number,
+            src_col: frame.col,
+            byte_offset: frame.byte_offset,
+            len: frame.kind.len(),
+        });
     }
 
     let mut code = output.join("\n");
@@ -440,10 +721,14 @@ pub fn transpile_ext(source: &str, include_injection: bool) -> TranspileResult {
         code.push('\n');
     }
 
+    let sourcemap_v3 = source_map_v3(&mappings, "template.hyper");
+
     TranspileResult {
         python_code: code,
         source_mappings: mappings,
         python_pieces,
+        diagnostics,
+        sourcemap_v3,
     }
 }
 
@@ -452,6 +737,293 @@ pub fn transpile(source: &str) -> TranspileResult {
     transpile_ext(source, false)
 }
 
+/// Split a `for` target expression (e.g. `a, b` or `(a, b)`) into the bare
+/// names it binds.
+fn binder_names_from_target(target: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    for part in split_top_level_commas(target.trim()) {
+        collect_binder_names(&part, &mut names);
+    }
+    names
+}
+
+/// Recursively walk a (possibly nested) tuple/list unpacking target, e.g.
+/// `(a, (b, c))`, collecting every bare identifier it binds.
+fn collect_binder_names(part: &str, names: &mut Vec<String>) {
+    let trimmed = part.trim().trim_start_matches('*').trim();
+    let inner = trimmed
+        .strip_prefix('(')
+        .and_then(|s| s.strip_suffix(')'))
+        .or_else(|| trimmed.strip_prefix('[').and_then(|s| s.strip_suffix(']')));
+
+    if let Some(inner) = inner {
+        for sub in split_top_level_commas(inner) {
+            collect_binder_names(&sub, names);
+        }
+    } else if trimmed.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_') {
+        names.push(trimmed.to_string());
+    }
+}
+
+/// Split on commas that aren't nested inside `()`/`[]`/`{}`, so a target like
+/// `a, (b, c)` yields `["a", " (b, c)"]` rather than splitting inside the
+/// nested tuple.
+fn split_top_level_commas(s: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0usize;
+
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(s[start..i].to_string());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(s[start..].to_string());
+    parts
+}
+
+/// Approximate the capture names bound by a `case` pattern: bare identifiers
+/// that aren't attribute access (`x.y`), a class pattern's callee (`Point(`),
+/// or a class pattern's keyword (`x=` in `Point(x=px)`).
+fn case_pattern_binders(pattern: &str) -> Vec<String> {
+    let mut binders = Vec::new();
+    for m in IDENT.find_iter(pattern) {
+        let name = m.as_str();
+        if name == "_" || name == "case" {
+            continue;
+        }
+        let prev = pattern[..m.start()].chars().last();
+        let mut rest = pattern[m.end()..].chars();
+        let next = rest.next();
+        if prev == Some('.') || next == Some('.') || next == Some('(') {
+            continue;
+        }
+        if next == Some('=') && rest.next() != Some('=') {
+            continue;
+        }
+        binders.push(name.to_string());
+    }
+    binders
+}
+
+/// Record every non-keyword, non-attribute identifier in `text` as
+/// referenced, for the "parameter never used" check.
+fn record_references(text: &str, referenced: &mut HashSet<String>) {
+    for m in IDENT.find_iter(text) {
+        let name = m.as_str();
+        if text[..m.start()].ends_with('.') || is_keyword_or_builtin(name) {
+            continue;
+        }
+        referenced.insert(name.to_string());
+    }
+}
+
+/// Find `{expr}` interpolations in an `Html` line, honoring `{{`/`}}`
+/// escapes and brace nesting (e.g. a dict literal inside the expression).
+/// Returns `(col, expr)` for each interpolation, where `col` is the byte
+/// offset of the expression's first character within `text`.
+fn find_interpolations(text: &str) -> Vec<(usize, String)> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' if text[i..].starts_with("{{") => i += 2,
+            b'}' if text[i..].starts_with("}}") => i += 2,
+            b'{' => {
+                let mut depth = 1;
+                let mut j = i + 1;
+                while j < bytes.len() && depth > 0 {
+                    match bytes[j] {
+                        b'{' => depth += 1,
+                        b'}' => depth -= 1,
+                        _ => {}
+                    }
+                    j += 1;
+                }
+                if depth == 0 {
+                    out.push((i + 1, text[i + 1..j - 1].to_string()));
+                }
+                i = j;
+            }
+            _ => i += 1,
+        }
+    }
+
+    out
+}
+
+/// Walk the transpiled template's structure to check that every identifier
+/// referenced inside a `{...}` interpolation has been declared by then, and
+/// flag template parameters that are never referenced. Mirrors the same
+/// `level`/`stack` indentation machinery `transpile_ext` uses for the body,
+/// but keeps a scope (a set of declared names) per open block instead of an
+/// indentation level.
+fn analyze(source: &str) -> Vec<Diagnostic> {
+    let lines = lex(source);
+    let (_, params, body_start) = find_structure(&lines);
+
+    let mut diagnostics = Vec::new();
+    let mut scopes: Vec<HashSet<String>> = vec![HashSet::new()];
+    let mut referenced: HashSet<String> = HashSet::new();
+    let mut declared_params = Vec::new();
+
+    for p in &params {
+        let (start, end) = content_bounds(&p.text);
+        if let Some(caps) = TYPE_ANNOTATION.captures(&p.text[start..end]) {
+            let name = caps.get(1).unwrap().as_str().to_string();
+            scopes[0].insert(name.clone());
+            declared_params.push((name, p.line_number, start, p.byte_offset + start));
+        }
+    }
+
+    let mut stack: Vec<&str> = Vec::new();
+
+    for line in &lines[body_start..] {
+        let (start, end) = content_bounds(&line.text);
+        if start >= end {
+            continue;
+        }
+        let trimmed = &line.text[start..end];
+
+        match line.line_type {
+            LineType::Control => {
+                let is_dedent = ["else", "elif", "except", "finally"]
+                    .iter()
+                    .any(|kw| trimmed.starts_with(kw));
+                let is_case = trimmed.starts_with("case");
+
+                record_references(trimmed, &mut referenced);
+
+                if is_dedent {
+                    // Reuses the enclosing block's scope; nothing to push,
+                    // but `except E as name:` still introduces a binder into
+                    // that shared scope for the rest of the except body.
+                    if trimmed.starts_with("except") {
+                        if let Some(scope) = scopes.last_mut() {
+                            for caps in AS_BINDER.captures_iter(trimmed) {
+                                scope.insert(caps[1].to_string());
+                            }
+                        }
+                    }
+                } else if is_case {
+                    if stack.last() == Some(&"case") {
+                        stack.pop();
+                        scopes.pop();
+                    }
+                    let mut scope = HashSet::new();
+                    for name in case_pattern_binders(trimmed) {
+                        scope.insert(name);
+                    }
+                    for caps in AS_BINDER.captures_iter(trimmed) {
+                        scope.insert(caps[1].to_string());
+                    }
+                    scopes.push(scope);
+                    stack.push("case");
+                } else {
+                    let mut scope = HashSet::new();
+                    if let Some(caps) = FOR_TARGET.captures(trimmed) {
+                        for name in binder_names_from_target(&caps[1]) {
+                            scope.insert(name);
+                        }
+                    }
+                    for caps in AS_BINDER.captures_iter(trimmed) {
+                        scope.insert(caps[1].to_string());
+                    }
+                    scopes.push(scope);
+                    stack.push(if trimmed.starts_with("match") { "match" } else { "block" });
+                }
+            }
+
+            LineType::End => {
+                while stack.last() == Some(&"case") {
+                    stack.pop();
+                    scopes.pop();
+                }
+                if !stack.is_empty() {
+                    stack.pop();
+                    scopes.pop();
+                }
+            }
+
+            LineType::Python => {
+                record_references(trimmed, &mut referenced);
+                if let Some(m) = ASSIGN_TARGET.captures(trimmed).filter(|caps| {
+                    // Exclude `==` (comparison), which the regex can't look ahead past.
+                    !trimmed[caps.get(0).unwrap().end()..].starts_with('=')
+                }) {
+                    for name in m[1].split(',') {
+                        scopes.last_mut().unwrap().insert(name.trim().to_string());
+                    }
+                }
+            }
+
+            LineType::Html => {
+                for (col, expr) in find_interpolations(trimmed) {
+                    for m in IDENT.find_iter(&expr) {
+                        let name = m.as_str();
+                        let prev = expr[..m.start()].chars().last();
+                        let mut rest = expr[m.end()..].chars();
+                        let next = rest.next();
+                        if prev == Some('.') || is_keyword_or_builtin(name) {
+                            continue;
+                        }
+                        if next == Some('=') && rest.next() != Some('=') {
+                            continue;
+                        }
+                        referenced.insert(name.to_string());
+                        if !scopes.iter().any(|s| s.contains(name)) {
+                            let src_col = start + col + m.start();
+                            diagnostics.push(Diagnostic {
+                                severity: Severity::Error,
+                                message: format!("`{}` is used before it is in scope", name),
+                                src_line: line.line_number,
+                                src_col,
+                                byte_offset: line.byte_offset + src_col,
+                                len: name.len(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    for (name, line_number, col, byte_offset) in &declared_params {
+        if !referenced.contains(name) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                message: format!("parameter `{}` is never referenced", name),
+                src_line: *line_number,
+                src_col: *col,
+                byte_offset: *byte_offset,
+                len: name.len(),
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// Transpile source and additionally run scope/symbol analysis: every
+/// identifier referenced inside a `{...}` interpolation must already be in
+/// scope (a template parameter, or bound by a `for`/`with`/`except`/`case`
+/// or plain assignment seen so far), and parameters that are never
+/// referenced are flagged too. Gives template authors typo detection on
+/// variables without running the generated Python.
+pub fn transpile_checked(source: &str) -> TranspileResult {
+    let mut result = transpile(source);
+    result.diagnostics.extend(analyze(source));
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -475,4 +1047,122 @@ mod tests {
         assert!(result.python_code.contains("for item in items:"));
         assert!(result.python_code.contains("pass"));
     }
+
+    #[test]
+    fn test_multiline_call_stays_one_statement() {
+        let result = transpile("items: list\n\ntotal = sum(\n    items\n)\n<div>{total}</div>\n");
+        assert!(result.python_code.contains("total = sum(\n    items\n)"));
+    }
+
+    #[test]
+    fn test_mappings_advance_past_multiline_statement() {
+        let result = transpile(
+            "items: list\n\nif items:\n    total = sum(\n        items\n    )\n    <div>{total}</div>\nend\n",
+        );
+        let by_gen_line = |gen_line: usize| {
+            result
+                .source_mappings
+                .iter()
+                .find(|m| m.gen_line == gen_line)
+                .map(|m| m.src_line)
+        };
+        // The 3-line `total = sum(...)` call occupies generated lines 3-5
+        // (no mapping of their own for lines 4-5), so `<div>` (source line
+        // 6) must land on generated line 6, not 4, and `end` on line 7.
+        assert_eq!(by_gen_line(3), Some(3));
+        assert_eq!(by_gen_line(4), None);
+        assert_eq!(by_gen_line(5), None);
+        assert_eq!(by_gen_line(6), Some(6));
+        assert_eq!(by_gen_line(7), Some(7));
+    }
+
+    #[test]
+    fn test_html_prose_apostrophe_and_unbalanced_paren_does_not_fold_lines() {
+        let result = transpile("<div>It's a nice day (really, {name}</div>\n<p>Second paragraph</p>\n");
+        assert!(result.python_code.contains("It's a nice day"));
+        assert!(result.python_code.contains("Second paragraph"));
+        // Each `<...>` line must stay its own statement, not get folded into
+        // the previous one by a stray apostrophe or unmatched paren, so both
+        // source lines are independently represented among the mappings.
+        assert!(result.source_mappings.iter().any(|m| m.src_line == 0));
+        assert!(result.source_mappings.iter().any(|m| m.src_line == 1));
+    }
+
+    #[test]
+    fn test_annotated_assignment_in_body_does_not_warn() {
+        let result = transpile("name: str\n\n<div>Hi</div>\ncount: int = 0\nprint(count)\n");
+        assert!(result.diagnostics.iter().all(|d| !d.message.contains("has no effect")));
+    }
+
+    #[test]
+    fn test_stray_parameter_annotation_in_body_still_warns() {
+        let result = transpile("name: str\n\n<div>Hi</div>\ncount: int\n");
+        assert!(result.diagnostics.iter().any(|d| d.message.contains("has no effect")));
+    }
+
+    #[test]
+    fn test_trailing_backslash_continuation() {
+        let result = transpile("x: int\n\ny = x + \\\n    1\n<div>{y}</div>\n");
+        assert!(result.python_code.contains("y = x + \\\n    1"));
+    }
+
+    #[test]
+    fn test_checked_flags_undeclared_identifier() {
+        let result = transpile_checked("name: str\n\n<div>Hello {nmae}</div>\n");
+        assert!(result
+            .diagnostics
+            .iter()
+            .any(|d| d.severity == Severity::Error && d.message.contains("nmae")));
+    }
+
+    #[test]
+    fn test_checked_allows_for_loop_binder() {
+        let result = transpile_checked("items: list\n\nfor item in items:\n    <li>{item}</li>\nend\n");
+        assert!(result.diagnostics.iter().all(|d| d.severity != Severity::Error));
+    }
+
+    #[test]
+    fn test_checked_allows_nested_tuple_for_loop_binders() {
+        let result = transpile_checked(
+            "pairs: list\n\nfor (a, (b, c)) in pairs:\n    <div>{a} {b} {c}</div>\nend\n",
+        );
+        assert!(result.diagnostics.iter().all(|d| d.severity != Severity::Error));
+    }
+
+    #[test]
+    fn test_checked_allows_except_as_binder() {
+        let result = transpile_checked("try:\n    y = 1\nexcept ValueError as e:\n    <div>{e}</div>\nend\n");
+        assert!(result.diagnostics.iter().all(|d| d.severity != Severity::Error));
+    }
+
+    #[test]
+    fn test_checked_warns_on_unused_param() {
+        let result = transpile_checked("name: str\n\n<div>Hello</div>\n");
+        assert!(result
+            .diagnostics
+            .iter()
+            .any(|d| d.severity == Severity::Warning && d.message.contains("name")));
+    }
+
+    #[test]
+    fn test_sourcemap_v3_has_one_group_per_generated_line() {
+        let result = transpile("name: str\n\n<div>Hello {name}</div>\n");
+        let map = &result.sourcemap_v3;
+        assert_eq!(map.version, 3);
+        assert_eq!(map.sources, vec!["template.hyper".to_string()]);
+        let line_count = result.python_code.lines().count();
+        assert_eq!(map.mappings.matches(';').count() + 1, line_count);
+    }
+
+    #[test]
+    fn test_vlq_matches_known_encodings() {
+        let encode = |v: i64| {
+            let mut out = String::new();
+            push_vlq(v, &mut out);
+            out
+        };
+        assert_eq!(encode(0), "A");
+        assert_eq!(encode(1), "C");
+        assert_eq!(encode(-1), "D");
+    }
 }
\ No newline at end of file
@@ -1,7 +1,11 @@
 use clap::{Parser, Subcommand};
-use hyper_transpiler::{transpile, transpile_ext};
+use hyper_transpiler::{
+    source_map_v3, transpile, transpile_checked, transpile_ext, Diagnostic, Severity, TranspileResult,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::io::{self, Read};
+use std::io::{self, BufRead, Read, Write};
 use std::path::PathBuf;
 use walkdir::WalkDir;
 
@@ -32,46 +36,62 @@ enum Commands {
         /// Include injection pieces for IDE integration
         #[arg(long)]
         injection: bool,
+
+        /// Run scope/symbol analysis and report undeclared or unused names
+        #[arg(long)]
+        check: bool,
+
+        /// Write a Source Map v3 `.py.map` file alongside the generated `.py`
+        #[arg(long)]
+        sourcemap: bool,
     },
+
+    /// Run a long-lived server over stdio for editor integration
+    Serve,
 }
 
 fn main() {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Generate { file, stdin, json, injection } => {
+        Commands::Generate { file, stdin, json, injection, check, sourcemap } => {
             if stdin {
-                generate_stdin(json, injection);
+                generate_stdin(json, injection, check);
             } else if let Some(path) = file {
-                generate_path(&path);
+                generate_path(&path, check, sourcemap);
             } else {
                 eprintln!("Error: provide a file/directory or use --stdin");
                 std::process::exit(1);
             }
         }
+        Commands::Serve => serve(),
     }
 }
 
-fn generate_stdin(json_output: bool, include_injection: bool) {
+fn generate_stdin(json_output: bool, include_injection: bool, check: bool) {
     let mut source = String::new();
     io::stdin().read_to_string(&mut source).expect("Failed to read stdin");
 
-    let result = transpile_ext(&source, include_injection);
+    let mut result = transpile_ext(&source, include_injection);
+    if check {
+        result.diagnostics = transpile_checked(&source).diagnostics;
+    }
 
     if json_output {
         println!("{}", serde_json::to_string(&result).unwrap());
     } else {
+        print_diagnostics(&source, &result.diagnostics);
         print!("{}", result.python_code);
     }
 }
 
-fn generate_path(path: &PathBuf) {
+fn generate_path(path: &PathBuf, check: bool, sourcemap: bool) {
     if path.is_file() {
         if path.extension().map_or(true, |ext| ext != "hyper") {
             eprintln!("Error: {} is not a .hyper file", path.display());
             std::process::exit(1);
         }
-        generate_file(path);
+        generate_file(path, check, sourcemap);
     } else if path.is_dir() {
         let mut found = false;
         for entry in WalkDir::new(path)
@@ -80,7 +100,7 @@ fn generate_path(path: &PathBuf) {
             .filter(|e| e.path().extension().map_or(false, |ext| ext == "hyper"))
         {
             found = true;
-            generate_file(entry.path());
+            generate_file(entry.path(), check, sourcemap);
         }
         if !found {
             eprintln!("No .hyper files found in {}", path.display());
@@ -92,10 +112,182 @@ fn generate_path(path: &PathBuf) {
     }
 }
 
-fn generate_file(path: &std::path::Path) {
+fn generate_file(path: &std::path::Path, check: bool, sourcemap: bool) {
     let source = fs::read_to_string(path).expect("Failed to read file");
-    let result = transpile(&source);
+    let result = if check { transpile_checked(&source) } else { transpile(&source) };
+    print_diagnostics(&source, &result.diagnostics);
     let output = path.with_extension("py");
     fs::write(&output, &result.python_code).expect("Failed to write file");
     println!("Generated {}", output.display());
+
+    if sourcemap {
+        let source_name = path.file_name().map_or_else(|| "template.hyper".to_string(), |n| n.to_string_lossy().into_owned());
+        let map = source_map_v3(&result.source_mappings, &source_name);
+        let map_path = output.with_extension("py.map");
+        fs::write(&map_path, serde_json::to_string(&map).unwrap()).expect("Failed to write source map");
+        println!("Generated {}", map_path.display());
+    }
+}
+
+#[derive(Deserialize)]
+struct ServeRequest {
+    id: u64,
+    method: String,
+    #[serde(default)]
+    params: ServeParams,
+}
+
+#[derive(Deserialize, Default)]
+struct ServeParams {
+    uri: String,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    injection: bool,
+}
+
+#[derive(Serialize)]
+struct ServeResponse {
+    id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<TranspileResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Speak a small newline-delimited JSON-RPC protocol over stdin/stdout: one
+/// JSON request per input line, one JSON response per output line. Keeps an
+/// in-memory map of open documents; each `didOpen`/`didChange` carries the
+/// document's full text, which replaces the stored copy (partial range edits
+/// are not accepted). The response holds `python_code`, `source_mappings`,
+/// `diagnostics`, and, when the request sets `injection`, `python_pieces`,
+/// without paying process-spawn cost on every edit.
+fn serve() {
+    let mut documents: HashMap<String, String> = HashMap::new();
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<ServeRequest>(&line) {
+            Ok(request) => handle_serve_request(&mut documents, request),
+            Err(err) => ServeResponse { id: 0, result: None, error: Some(err.to_string()) },
+        };
+
+        let _ = writeln!(stdout, "{}", serde_json::to_string(&response).unwrap());
+        let _ = stdout.flush();
+    }
+}
+
+fn handle_serve_request(documents: &mut HashMap<String, String>, request: ServeRequest) -> ServeResponse {
+    let id = request.id;
+
+    match request.method.as_str() {
+        "didOpen" | "didChange" => {
+            documents.insert(request.params.uri.clone(), request.params.text.clone());
+            let result = transpile_ext(&request.params.text, request.params.injection);
+            ServeResponse { id, result: Some(result), error: None }
+        }
+        "didClose" => {
+            documents.remove(&request.params.uri);
+            ServeResponse { id, result: None, error: None }
+        }
+        other => ServeResponse { id, result: None, error: Some(format!("unknown method `{}`", other)) },
+    }
+}
+
+/// Render diagnostics as `rustc`-style caret-underlined snippets, in the
+/// spirit of the `annotate-snippets` crate: a header with the message, the
+/// offending source line, and a `^^^` underline under the flagged span.
+fn print_diagnostics(source: &str, diagnostics: &[Diagnostic]) {
+    let lines: Vec<&str> = source.lines().collect();
+
+    for diag in diagnostics {
+        let label = match diag.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        eprintln!("{}: {}", label, diag.message);
+        eprintln!("  --> line {}:{}", diag.src_line + 1, diag.src_col + 1);
+
+        if let Some(src_line) = lines.get(diag.src_line) {
+            eprintln!("   | {}", src_line);
+            let underline_len = diag.len.max(1);
+            eprintln!(
+                "   | {}{}",
+                " ".repeat(diag.src_col),
+                "^".repeat(underline_len)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(id: u64, method: &str, uri: &str, text: &str) -> ServeRequest {
+        ServeRequest {
+            id,
+            method: method.to_string(),
+            params: ServeParams { uri: uri.to_string(), text: text.to_string(), injection: false },
+        }
+    }
+
+    #[test]
+    fn test_did_open_transpiles_and_stores_document() {
+        let mut documents = HashMap::new();
+        let response = handle_serve_request(
+            &mut documents,
+            request(1, "didOpen", "file:///a.hyper", "name: str\n\n<div>Hello {name}</div>\n"),
+        );
+
+        assert_eq!(response.id, 1);
+        assert!(response.error.is_none());
+        assert!(response.result.unwrap().python_code.contains("name"));
+        assert_eq!(documents.get("file:///a.hyper").map(String::as_str), Some("name: str\n\n<div>Hello {name}</div>\n"));
+    }
+
+    #[test]
+    fn test_did_change_retranspiles_and_updates_document() {
+        let mut documents = HashMap::new();
+        documents.insert("file:///a.hyper".to_string(), "name: str\n\n<div>{name}</div>\n".to_string());
+
+        let response = handle_serve_request(
+            &mut documents,
+            request(2, "didChange", "file:///a.hyper", "name: str\n\n<div>Bye {name}</div>\n"),
+        );
+
+        assert_eq!(response.id, 2);
+        assert!(response.error.is_none());
+        assert!(response.result.unwrap().python_code.contains("Bye"));
+        assert_eq!(documents.get("file:///a.hyper").map(String::as_str), Some("name: str\n\n<div>Bye {name}</div>\n"));
+    }
+
+    #[test]
+    fn test_did_close_removes_document() {
+        let mut documents = HashMap::new();
+        documents.insert("file:///a.hyper".to_string(), "<div>Hi</div>\n".to_string());
+
+        let response = handle_serve_request(&mut documents, request(3, "didClose", "file:///a.hyper", ""));
+
+        assert_eq!(response.id, 3);
+        assert!(response.result.is_none());
+        assert!(response.error.is_none());
+        assert!(!documents.contains_key("file:///a.hyper"));
+    }
+
+    #[test]
+    fn test_unknown_method_returns_error() {
+        let mut documents = HashMap::new();
+        let response = handle_serve_request(&mut documents, request(4, "didSave", "file:///a.hyper", ""));
+
+        assert_eq!(response.id, 4);
+        assert!(response.result.is_none());
+        assert!(response.error.unwrap().contains("didSave"));
+    }
 }
\ No newline at end of file